@@ -5,36 +5,165 @@ use revm::{
 };
 use sha3::{Digest, Keccak256};
 use std::{str::FromStr, sync::Arc};
-use alloy::{primitives::{Address, B256}, providers::RootProvider, transports::http::{Client, Http}};
+use alloy::{eips::{eip2930::AccessListItem, BlockId}, primitives::{Address, B256}, providers::RootProvider, transports::http::{Client, Http}};
 
-use crate::provider::Backend;
+use crate::provider::{self, Backend, BackendError};
 
 pub struct ForkDB {
     db: CacheDB<EmptyDB>,
     provider: Arc<dyn EthProvider>,
+    account_overrides: rHashMap<rAddress, AccountOverride>,
+    code_overrides: rHashMap<rB256, rBytecode>,
+    storage_overrides: rHashMap<(rAddress, rU256), rU256>,
+}
+
+/// A partial override for an account's balance, nonce, and/or code. `None`
+/// fields fall through to the forked value.
+#[derive(Clone, Debug, Default)]
+pub struct AccountOverride {
+    pub balance: Option<rU256>,
+    pub nonce: Option<u64>,
+    pub code: Option<Vec<u8>>,
 }
 
 pub trait EthProvider: Send + Sync {
-    fn get_basic(&self, address: rAddress) -> Option<AccountInfo>;
-    fn get_storage(&self, address: rAddress, index: rU256) -> rU256;
-    fn get_block_hash(&self, number: rU256) -> rB256;
+    fn get_basic(&self, address: rAddress) -> Result<Option<AccountInfo>, BackendError>;
+    fn get_storage(&self, address: rAddress, index: rU256) -> Result<rU256, BackendError>;
+    fn get_block_hash(&self, number: rU256) -> Result<rB256, BackendError>;
+    fn prefetch(&self, accounts: &[(rAddress, Vec<rU256>)]) -> Result<Vec<PrefetchedValue>, BackendError>;
+}
+
+/// One entry of a batch [`EthProvider::prefetch`] result.
+pub enum PrefetchedValue {
+    Account(rAddress, AccountInfo),
+    Storage(rAddress, rU256, rU256),
 }
 
 impl ForkDB {
     pub fn new(db: CacheDB<EmptyDB>, provider: Arc<dyn EthProvider>) -> Self {
-        Self { db, provider }
+        Self {
+            db,
+            provider,
+            account_overrides: rHashMap::default(),
+            code_overrides: rHashMap::default(),
+            storage_overrides: rHashMap::default(),
+        }
+    }
+
+    /// Forks at a fixed `block` instead of the node's current tip.
+    pub fn new_at_block(db: CacheDB<EmptyDB>, provider: RootProvider<Http<Client>>, block: BlockId) -> Self {
+        Self::new(db, create_eth_provider_at(provider, block))
+    }
+
+    /// Prefetches every account and storage slot in `access_list` into the cache.
+    pub fn warm(&mut self, access_list: &[AccessListItem]) -> Result<(), BackendError> {
+        let accounts: Vec<(rAddress, Vec<rU256>)> = access_list
+            .iter()
+            .map(|item| {
+                let slots = item.storage_keys.iter().map(|key| rU256::from_be_bytes(key.0)).collect();
+                (rAddress::from(item.address.0), slots)
+            })
+            .collect();
+
+        for value in self.provider.prefetch(&accounts)? {
+            match value {
+                PrefetchedValue::Account(address, info) => {
+                    if self.full_account_override(address).is_some() {
+                        continue;
+                    }
+                    let info = self.apply_account_override(address, Some(info)).unwrap();
+                    self.db.insert_account_info(address, info);
+                }
+                PrefetchedValue::Storage(address, index, value) => {
+                    if self.storage_overrides.contains_key(&(address, index)) {
+                        continue;
+                    }
+                    let _ = self.db.insert_account_storage(address, index, value);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Overrides `address`'s balance, nonce, and/or code. Evicts any cached
+    /// info for `address` so the override takes effect on the next read.
+    pub fn override_account(&mut self, address: rAddress, account_override: AccountOverride) {
+        if let Some(code) = &account_override.code {
+            let code_hash = rB256::from(keccak256(code));
+            self.code_overrides.insert(code_hash, rBytecode::new_raw(code.clone().into()));
+        }
+        self.account_overrides.insert(address, account_override);
+        self.db.accounts.remove(&address);
+    }
+
+    /// Overrides a single storage slot of `address`.
+    pub fn override_storage(&mut self, address: rAddress, slot: rU256, value: rU256) {
+        self.storage_overrides.insert((address, slot), value);
+        if let Some(account) = self.db.accounts.get_mut(&address) {
+            account.storage.remove(&slot);
+        }
+    }
+
+    /// An override that fully specifies balance, nonce, and code needs no
+    /// forked data at all, so reads for it can skip the provider entirely.
+    fn full_account_override(&self, address: rAddress) -> Option<AccountInfo> {
+        let over = self.account_overrides.get(&address)?;
+        let (Some(balance), Some(nonce), Some(code)) = (over.balance, over.nonce, &over.code) else {
+            return None;
+        };
+
+        Some(AccountInfo {
+            balance,
+            nonce,
+            code_hash: rB256::from(keccak256(code)),
+            code: Some(rBytecode::new_raw(code.clone().into())),
+        })
+    }
+
+    /// Merges any override for `address` onto `base` (the value read from
+    /// the fork), falling through to `base` field-by-field where the
+    /// override leaves a field unset.
+    fn apply_account_override(&self, address: rAddress, base: Option<AccountInfo>) -> Option<AccountInfo> {
+        let Some(over) = self.account_overrides.get(&address) else {
+            return base;
+        };
+
+        let mut info = base.unwrap_or_else(|| AccountInfo {
+            balance: rU256::ZERO,
+            nonce: 0,
+            code: None,
+            code_hash: KECCAK_EMPTY,
+        });
+
+        if let Some(balance) = over.balance {
+            info.balance = balance;
+        }
+        if let Some(nonce) = over.nonce {
+            info.nonce = nonce;
+        }
+        if let Some(code) = &over.code {
+            info.code_hash = rB256::from(keccak256(code));
+            info.code = Some(rBytecode::new_raw(code.clone().into()));
+        }
+        Some(info)
     }
 }
 
 impl Database for ForkDB {
-    type Error = Box<dyn std::error::Error>;
+    type Error = BackendError;
 
     fn basic(&mut self, address: rAddress) -> Result<Option<AccountInfo>, Self::Error> {
         if let Some(account) = self.db.accounts.get(&address) {
             return Ok(Some(account.info.clone()));
         }
-        
-        let info = self.provider.get_basic(address);
+
+        if let Some(info) = self.full_account_override(address) {
+            self.db.insert_account_info(address, info.clone());
+            return Ok(Some(info));
+        }
+
+        let info = self.provider.get_basic(address)?;
+        let info = self.apply_account_override(address, info);
         if let Some(info) = info.clone() {
             self.db.insert_account_info(address, info);
         }
@@ -48,7 +177,13 @@ impl Database for ForkDB {
             }
         }
 
-        let storage_val = self.provider.get_storage(address, index);
+        if let Some(value) = self.storage_overrides.get(&(address, index)) {
+            let value = *value;
+            self.db.insert_account_storage(address, index, value).unwrap();
+            return Ok(value);
+        }
+
+        let storage_val = self.provider.get_storage(address, index)?;
         self.db.insert_account_storage(address, index, storage_val).unwrap();
         Ok(storage_val)
     }
@@ -59,25 +194,31 @@ impl Database for ForkDB {
             return Ok(*hash);
         }
 
-        let block_hash = self.provider.get_block_hash(number);
+        let block_hash = self.provider.get_block_hash(number)?;
         self.db.block_hashes.insert(number, block_hash);
         Ok(block_hash)
     }
 
     fn code_by_hash(&mut self, code_hash: rB256) -> Result<rBytecode, Self::Error> {
-        self.db.code_by_hash(code_hash).map_err(|_| Box::new(std::io::Error::new(std::io::ErrorKind::NotFound, "Missing code")) as Box<dyn std::error::Error>)
+        if let Some(code) = self.code_overrides.get(&code_hash) {
+            return Ok(code.clone());
+        }
+        self.db.code_by_hash(code_hash).map_err(|_| BackendError::CodeNotFound)
     }
 }
 
 impl DatabaseRef for ForkDB {
-    type Error = Box<dyn std::error::Error>;
+    type Error = BackendError;
 
     fn basic_ref(&self, address: rAddress) -> Result<Option<AccountInfo>, Self::Error> {
         if let Some(account) = self.db.accounts.get(&address) {
-            Ok(Some(account.info.clone()))
-        } else {
-            Ok(self.provider.get_basic(address))
+            return Ok(Some(account.info.clone()));
         }
+        if let Some(info) = self.full_account_override(address) {
+            return Ok(Some(info));
+        }
+        let info = self.provider.get_basic(address)?;
+        Ok(self.apply_account_override(address, info))
     }
 
     fn storage_ref(&self, address: rAddress, index: rU256) -> Result<rU256, Self::Error> {
@@ -86,18 +227,24 @@ impl DatabaseRef for ForkDB {
                 return Ok(*entry);
             }
         }
-        Ok(self.provider.get_storage(address, index))
+        if let Some(value) = self.storage_overrides.get(&(address, index)) {
+            return Ok(*value);
+        }
+        self.provider.get_storage(address, index)
     }
 
     fn block_hash_ref(&self, number: u64) -> Result<rB256, Self::Error> {
         if number > u64::MAX {
             return Ok(KECCAK_EMPTY);
         }
-        Ok(self.provider.get_block_hash(rU256::from(number)))
+        self.provider.get_block_hash(rU256::from(number))
     }
 
-    fn code_by_hash_ref(&self, _code_hash: rB256) -> Result<rBytecode, Self::Error> {
-        Err(Box::new(std::io::Error::new(std::io::ErrorKind::NotFound, "Missing code")) as Box<dyn std::error::Error>)
+    fn code_by_hash_ref(&self, code_hash: rB256) -> Result<rBytecode, Self::Error> {
+        if let Some(code) = self.code_overrides.get(&code_hash) {
+            return Ok(code.clone());
+        }
+        Err(BackendError::CodeNotFound)
     }
 }
 
@@ -108,32 +255,64 @@ impl DatabaseCommit for ForkDB {
 }
 
 impl EthProvider for Backend {
-    fn get_basic(&self, address: rAddress) -> Option<AccountInfo> {
+    fn get_basic(&self, address: rAddress) -> Result<Option<AccountInfo>, BackendError> {
         let alloy_address = Address::from(address.0);
-        self.get_account(alloy_address).ok().map(|acc| {
-            let code_hash = rB256::from(keccak256(&acc.code));
-            AccountInfo {
-                balance: rU256::from_str(&acc.balance.to_string()).unwrap(),
-                nonce: acc.nonce,
-                code: Some(rBytecode::new_raw(acc.code.into())),
-                code_hash,
-            }
-        })
+        let acc = self.get_account(alloy_address)?;
+        let code_hash = rB256::from(keccak256(&acc.code));
+        Ok(Some(AccountInfo {
+            balance: rU256::from_str(&acc.balance.to_string()).unwrap(),
+            nonce: acc.nonce,
+            code: Some(rBytecode::new_raw(acc.code.into())),
+            code_hash,
+        }))
     }
 
-    fn get_storage(&self, address: rAddress, index: rU256) -> rU256 {
+    fn get_storage(&self, address: rAddress, index: rU256) -> Result<rU256, BackendError> {
         let alloy_address = Address::from(address.0);
         let alloy_index = B256::from(index.to_be_bytes());
-        self.get_storage_at(alloy_address, alloy_index)
-            .map(|v| rU256::from_str(&v.to_string()).unwrap())
-            .unwrap_or_default()
+        let value = self.get_storage_at(alloy_address, alloy_index)?;
+        Ok(rU256::from_str(&value.to_string()).unwrap())
     }
 
-    fn get_block_hash(&self, number: rU256) -> rB256 {
+    fn get_block_hash(&self, number: rU256) -> Result<rB256, BackendError> {
         let alloy_number = rU256::from_str(&number.to_string()).unwrap();
-        self.get_block_hash(alloy_number)
-            .map(|h| rB256::from(h.0))
-            .unwrap_or_default()
+        let hash = self.get_block_hash(alloy_number)?;
+        Ok(rB256::from(hash.0))
+    }
+
+    fn prefetch(&self, accounts: &[(rAddress, Vec<rU256>)]) -> Result<Vec<PrefetchedValue>, BackendError> {
+        let accounts: Vec<(Address, Vec<B256>)> = accounts
+            .iter()
+            .map(|(address, slots)| {
+                let slots = slots.iter().map(|slot| B256::from(slot.to_be_bytes())).collect();
+                (Address::from(address.0), slots)
+            })
+            .collect();
+
+        let values = self.prefetch(&accounts)?;
+
+        Ok(values
+            .into_iter()
+            .map(|value| match value {
+                provider::PrefetchedValue::Account(address, acc) => {
+                    let code_hash = rB256::from(keccak256(&acc.code));
+                    PrefetchedValue::Account(
+                        rAddress::from(address.0),
+                        AccountInfo {
+                            balance: rU256::from_str(&acc.balance.to_string()).unwrap(),
+                            nonce: acc.nonce,
+                            code: Some(rBytecode::new_raw(acc.code.into())),
+                            code_hash,
+                        },
+                    )
+                }
+                provider::PrefetchedValue::Storage(address, slot, value) => PrefetchedValue::Storage(
+                    rAddress::from(address.0),
+                    rU256::from_be_bytes(slot.0),
+                    rU256::from_str(&value.to_string()).unwrap(),
+                ),
+            })
+            .collect())
     }
 }
 
@@ -144,4 +323,113 @@ fn keccak256(data: &[u8]) -> [u8; 32] {
 }
 pub fn create_eth_provider(provider: RootProvider<Http<Client>>) -> Arc<dyn EthProvider> {
     Arc::new(Backend::new(provider))
+}
+
+/// Like [`create_eth_provider`], but pinned to `block`.
+pub fn create_eth_provider_at(provider: RootProvider<Http<Client>>, block: BlockId) -> Arc<dyn EthProvider> {
+    Arc::new(Backend::new_at_block(provider, block))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    /// An [`EthProvider`] backed by in-memory maps instead of a live RPC
+    /// endpoint, so override precedence can be tested without a node.
+    #[derive(Default)]
+    struct FakeProvider {
+        accounts: HashMap<rAddress, AccountInfo>,
+        storage: HashMap<(rAddress, rU256), rU256>,
+    }
+
+    impl EthProvider for FakeProvider {
+        fn get_basic(&self, address: rAddress) -> Result<Option<AccountInfo>, BackendError> {
+            Ok(self.accounts.get(&address).cloned())
+        }
+
+        fn get_storage(&self, address: rAddress, index: rU256) -> Result<rU256, BackendError> {
+            Ok(self.storage.get(&(address, index)).copied().unwrap_or(rU256::ZERO))
+        }
+
+        fn get_block_hash(&self, _number: rU256) -> Result<rB256, BackendError> {
+            Ok(rB256::ZERO)
+        }
+
+        fn prefetch(&self, accounts: &[(rAddress, Vec<rU256>)]) -> Result<Vec<PrefetchedValue>, BackendError> {
+            let mut values = Vec::new();
+            for (address, slots) in accounts {
+                let info = self.accounts.get(address).cloned().unwrap_or(AccountInfo {
+                    balance: rU256::ZERO,
+                    nonce: 0,
+                    code: None,
+                    code_hash: KECCAK_EMPTY,
+                });
+                values.push(PrefetchedValue::Account(*address, info));
+                for slot in slots {
+                    let value = self.storage.get(&(*address, *slot)).copied().unwrap_or(rU256::ZERO);
+                    values.push(PrefetchedValue::Storage(*address, *slot, value));
+                }
+            }
+            Ok(values)
+        }
+    }
+
+    fn fork_db_with(provider: FakeProvider) -> ForkDB {
+        ForkDB::new(CacheDB::new(EmptyDB::default()), Arc::new(provider))
+    }
+
+    #[test]
+    fn full_override_skips_the_provider() {
+        let address = rAddress::from([1u8; 20]);
+        let mut db = fork_db_with(FakeProvider::default());
+        db.override_account(
+            address,
+            AccountOverride { balance: Some(rU256::from(42u64)), nonce: Some(7), code: Some(vec![0x60, 0x00]) },
+        );
+
+        let info = db.basic(address).unwrap().unwrap();
+        assert_eq!(info.balance, rU256::from(42u64));
+        assert_eq!(info.nonce, 7);
+    }
+
+    #[test]
+    fn partial_override_falls_through_to_the_forked_value() {
+        let address = rAddress::from([2u8; 20]);
+        let mut provider = FakeProvider::default();
+        provider.accounts.insert(address, AccountInfo { balance: rU256::from(100u64), nonce: 3, code: None, code_hash: KECCAK_EMPTY });
+        let mut db = fork_db_with(provider);
+        db.override_account(address, AccountOverride { balance: Some(rU256::from(999u64)), nonce: None, code: None });
+
+        let info = db.basic(address).unwrap().unwrap();
+        assert_eq!(info.balance, rU256::from(999u64));
+        assert_eq!(info.nonce, 3);
+    }
+
+    #[test]
+    fn storage_override_takes_precedence_over_the_fork() {
+        let address = rAddress::from([3u8; 20]);
+        let slot = rU256::from(1u64);
+        let mut provider = FakeProvider::default();
+        provider.storage.insert((address, slot), rU256::from(5u64));
+        let mut db = fork_db_with(provider);
+        assert_eq!(db.storage(address, slot).unwrap(), rU256::from(5u64));
+
+        db.override_storage(address, slot, rU256::from(123u64));
+        assert_eq!(db.storage(address, slot).unwrap(), rU256::from(123u64));
+    }
+
+    #[test]
+    fn warm_does_not_clobber_an_existing_override() {
+        let address = rAddress::from([4u8; 20]);
+        let mut provider = FakeProvider::default();
+        provider.accounts.insert(address, AccountInfo { balance: rU256::from(1u64), nonce: 0, code: None, code_hash: KECCAK_EMPTY });
+        let mut db = fork_db_with(provider);
+        db.override_account(address, AccountOverride { balance: Some(rU256::from(999u64)), nonce: None, code: None });
+
+        db.warm(&[AccessListItem { address: Address::from(address.0), storage_keys: vec![] }]).unwrap();
+
+        let info = db.basic(address).unwrap().unwrap();
+        assert_eq!(info.balance, rU256::from(999u64));
+    }
 }
\ No newline at end of file
@@ -0,0 +1,4 @@
+pub mod fork_db;
+pub mod provider;
+pub mod simulator;
+pub mod tracer;
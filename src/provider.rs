@@ -1,6 +1,7 @@
 use std::collections::HashMap;
 use std::thread;
-use std::error::Error;
+use std::fmt;
+use futures::future::join_all;
 use tokio::runtime::Runtime;
 use tokio::sync::mpsc::{channel, Sender, Receiver};
 use tokio::sync::oneshot;
@@ -9,9 +10,45 @@ use alloy::{
     primitives::{Address, B256, U256},
     providers::{Provider, RootProvider},
     rpc::types::BlockTransactionsKind,
-    transports::http::{Client, Http},
+    transports::{http::{Client, Http}, TransportError},
 };
 
+/// Batch size for fanning out `prefetch` requests.
+const PARALLEL_QUERY_BATCH_SIZE: usize = 20;
+
+/// Errors surfaced by [`Backend`]. A transport failure or a dropped backend
+/// thread is reported as one of these instead of being swallowed into a
+/// default value or a panic.
+#[derive(Debug)]
+pub enum BackendError {
+    Transport(TransportError),
+    BlockNotFound,
+    /// The backend's worker thread has exited, so the request channel (or
+    /// the oneshot reply) was dropped before a response arrived.
+    BackendUnavailable,
+    /// No bytecode is cached for the requested code hash.
+    CodeNotFound,
+}
+
+impl fmt::Display for BackendError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BackendError::Transport(err) => write!(f, "provider transport error: {err}"),
+            BackendError::BlockNotFound => write!(f, "block not found"),
+            BackendError::BackendUnavailable => write!(f, "backend thread is no longer running"),
+            BackendError::CodeNotFound => write!(f, "missing bytecode for code hash"),
+        }
+    }
+}
+
+impl std::error::Error for BackendError {}
+
+impl From<TransportError> for BackendError {
+    fn from(err: TransportError) -> Self {
+        BackendError::Transport(err)
+    }
+}
+
 #[derive(Clone)]
 pub struct AccountInfo {
     pub balance: U256,
@@ -23,123 +60,207 @@ enum BackendRequest {
     GetAccount(Address),
     GetStorageAt(Address, B256),
     GetBlockHash(U256),
+    Prefetch(Vec<Query>),
 }
 
 enum BackendResponse {
-    Account(Result<AccountInfo, Box<dyn Error + Send + Sync>>),
-    Storage(Result<U256, Box<dyn Error + Send + Sync>>),
-    BlockHash(Result<B256, Box<dyn Error + Send + Sync>>),
+    Account(Result<AccountInfo, BackendError>),
+    Storage(Result<U256, BackendError>),
+    BlockHash(Result<B256, BackendError>),
+    Prefetch(Result<Vec<PrefetchedValue>, BackendError>),
+}
+
+/// One entry of a batch [`Backend::prefetch`] result.
+pub enum PrefetchedValue {
+    Account(Address, AccountInfo),
+    Storage(Address, B256, U256),
+}
+
+#[derive(Clone, Copy)]
+enum Query {
+    Account(Address),
+    Storage(Address, B256),
 }
 
 pub struct Backend {
     sender: Sender<(BackendRequest, oneshot::Sender<BackendResponse>)>,
+    block: BlockId,
 }
 
 impl Backend {
+    /// Forks from whatever the node considers the current tip.
     pub fn new(provider: RootProvider<Http<Client>>) -> Self {
+        Self::new_at_block(provider, BlockId::latest())
+    }
+
+    /// Pins every account/storage read to `block` instead of the node's tip.
+    pub fn new_at_block(provider: RootProvider<Http<Client>>, block: BlockId) -> Self {
         let (sender, receiver) = channel(100);
-        let backend = Self { sender };
+        let backend = Self { sender, block };
 
         thread::spawn(move || {
             let rt = Runtime::new().unwrap();
             rt.block_on(async {
                 let mut db = HashMap::new();
-                backend_loop(provider, receiver, &mut db).await;
+                backend_loop(provider, block, receiver, &mut db).await;
             });
         });
 
         backend
     }
 
-    pub fn get_account(&self, address: Address) -> Result<AccountInfo, Box<dyn Error + Send + Sync>> {
-        let (response_sender, response_receiver) = oneshot::channel();
-        self.sender.blocking_send((BackendRequest::GetAccount(address), response_sender)).unwrap();
-        match response_receiver.blocking_recv().unwrap() {
+    /// The block every account/storage read on this backend is pinned to.
+    pub fn block(&self) -> BlockId {
+        self.block
+    }
+
+    pub fn get_account(&self, address: Address) -> Result<AccountInfo, BackendError> {
+        match self.request(BackendRequest::GetAccount(address))? {
             BackendResponse::Account(result) => result,
             _ => unreachable!(),
         }
     }
 
-    pub fn get_storage_at(&self, address: Address, slot: B256) -> Result<U256, Box<dyn Error + Send + Sync>> {
-        let (response_sender, response_receiver) = oneshot::channel();
-        self.sender.blocking_send((BackendRequest::GetStorageAt(address, slot), response_sender)).unwrap();
-        match response_receiver.blocking_recv().unwrap() {
+    pub fn get_storage_at(&self, address: Address, slot: B256) -> Result<U256, BackendError> {
+        match self.request(BackendRequest::GetStorageAt(address, slot))? {
             BackendResponse::Storage(result) => result,
             _ => unreachable!(),
         }
     }
 
-    pub fn get_block_hash(&self, number: U256) -> Result<B256, Box<dyn Error + Send + Sync>> {
-        let (response_sender, response_receiver) = oneshot::channel();
-        self.sender.blocking_send((BackendRequest::GetBlockHash(number), response_sender)).unwrap();
-        match response_receiver.blocking_recv().unwrap() {
+    pub fn get_block_hash(&self, number: U256) -> Result<B256, BackendError> {
+        match self.request(BackendRequest::GetBlockHash(number))? {
             BackendResponse::BlockHash(result) => result,
             _ => unreachable!(),
         }
     }
+
+    fn request(&self, request: BackendRequest) -> Result<BackendResponse, BackendError> {
+        let (response_sender, response_receiver) = oneshot::channel();
+        self.sender
+            .blocking_send((request, response_sender))
+            .map_err(|_| BackendError::BackendUnavailable)?;
+        response_receiver.blocking_recv().map_err(|_| BackendError::BackendUnavailable)
+    }
+
+    /// Fetches every account and storage slot in `accounts` in parallel batches of
+    /// `PARALLEL_QUERY_BATCH_SIZE`.
+    pub fn prefetch(&self, accounts: &[(Address, Vec<B256>)]) -> Result<Vec<PrefetchedValue>, BackendError> {
+        let mut queries = Vec::new();
+        for (address, slots) in accounts {
+            queries.push(Query::Account(*address));
+            queries.extend(slots.iter().map(|slot| Query::Storage(*address, *slot)));
+        }
+
+        match self.request(BackendRequest::Prefetch(queries))? {
+            BackendResponse::Prefetch(result) => result,
+            _ => unreachable!(),
+        }
+    }
 }
 
 async fn backend_loop(
     provider: RootProvider<Http<Client>>,
+    block: BlockId,
     mut receiver: Receiver<(BackendRequest, oneshot::Sender<BackendResponse>)>,
     db: &mut HashMap<Address, AccountInfo>,
 ) {
     while let Some((request, response_sender)) = receiver.recv().await {
         match request {
             BackendRequest::GetAccount(address) => {
-                let result = get_account(&provider, db, address).await;
+                let result = get_account(&provider, block, db, address).await;
                 let _ = response_sender.send(BackendResponse::Account(result));
             }
             BackendRequest::GetStorageAt(address, slot) => {
-                let result = get_storage_at(&provider, address, slot).await;
+                let result = get_storage_at(&provider, block, address, slot).await;
                 let _ = response_sender.send(BackendResponse::Storage(result));
             }
             BackendRequest::GetBlockHash(number) => {
                 let result = get_block_hash(&provider, number).await;
                 let _ = response_sender.send(BackendResponse::BlockHash(result));
             }
+            BackendRequest::Prefetch(queries) => {
+                let result = prefetch_queries(&provider, block, queries).await;
+                let _ = response_sender.send(BackendResponse::Prefetch(result));
+            }
+        }
+    }
+}
+
+async fn prefetch_queries(
+    provider: &RootProvider<Http<Client>>,
+    block: BlockId,
+    queries: Vec<Query>,
+) -> Result<Vec<PrefetchedValue>, BackendError> {
+    let mut values = Vec::with_capacity(queries.len());
+    for chunk in queries.chunks(PARALLEL_QUERY_BATCH_SIZE) {
+        let fetched = join_all(chunk.iter().map(|query| async move {
+            match *query {
+                Query::Account(address) => {
+                    fetch_account_info(provider, block, address).await
+                        .map(|info| PrefetchedValue::Account(address, info))
+                }
+                Query::Storage(address, slot) => {
+                    get_storage_at(provider, block, address, slot).await
+                        .map(|value| PrefetchedValue::Storage(address, slot, value))
+                }
+            }
+        })).await;
+
+        for value in fetched {
+            values.push(value?);
         }
     }
+    Ok(values)
 }
 
 async fn get_account(
     provider: &RootProvider<Http<Client>>,
+    block: BlockId,
     db: &mut HashMap<Address, AccountInfo>,
     address: Address,
-) -> Result<AccountInfo, Box<dyn Error + Send + Sync>> {
+) -> Result<AccountInfo, BackendError> {
     if let Some(account) = db.get(&address) {
         return Ok(account.clone());
     }
 
-    let balance = provider.get_balance(address).await.map_err(|e| Box::new(e) as Box<dyn Error + Send + Sync>)?;
-    let nonce = provider.get_transaction_count(address).await.map_err(|e| Box::new(e) as Box<dyn Error + Send + Sync>)?;
-    let code = provider.get_code_at(address).await.map_err(|e| Box::new(e) as Box<dyn Error + Send + Sync>)?;
+    let account_info = fetch_account_info(provider, block, address).await?;
+    db.insert(address, account_info.clone());
+    Ok(account_info)
+}
+
+async fn fetch_account_info(
+    provider: &RootProvider<Http<Client>>,
+    block: BlockId,
+    address: Address,
+) -> Result<AccountInfo, BackendError> {
+    let balance = provider.get_balance(address).block_id(block).await?;
+    let nonce = provider.get_transaction_count(address).block_id(block).await?;
+    let code = provider.get_code_at(address).block_id(block).await?;
 
-    let account_info = AccountInfo {
+    Ok(AccountInfo {
         balance,
         nonce,
         code: code.to_vec(),
-    };
-
-    db.insert(address, account_info.clone());
-    Ok(account_info)
+    })
 }
 
 async fn get_storage_at(
     provider: &RootProvider<Http<Client>>,
+    block: BlockId,
     address: Address,
     slot: B256,
-) -> Result<U256, Box<dyn Error + Send + Sync>> {
-    provider.get_storage_at(address, slot.into()).await.map_err(|e| Box::new(e) as Box<dyn Error + Send + Sync>)
+) -> Result<U256, BackendError> {
+    Ok(provider.get_storage_at(address, slot.into()).block_id(block).await?)
 }
 
 async fn get_block_hash(
     provider: &RootProvider<Http<Client>>,
     number: U256,
-) -> Result<B256, Box<dyn Error + Send + Sync>> {
+) -> Result<B256, BackendError> {
     let block = provider.get_block(
         BlockId::Number(BlockNumberOrTag::Number(number.to::<u64>())),
         BlockTransactionsKind::Hashes
-    ).await.map_err(|e| Box::new(e) as Box<dyn Error + Send + Sync>)?;
-    block.ok_or_else(|| Box::<dyn Error + Send + Sync>::from("Block not found")).map(|b| b.header.hash.unwrap_or_default())
-}
\ No newline at end of file
+    ).await?;
+    block.ok_or(BackendError::BlockNotFound).map(|b| b.header.hash.unwrap_or_default())
+}
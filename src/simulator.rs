@@ -0,0 +1,224 @@
+use revm::{
+    primitives::{Address, Bytes, EVMError, ExecutionResult, HaltReason, Output, TxKind, U256},
+    Evm,
+};
+
+use crate::{fork_db::ForkDB, provider::BackendError};
+
+/// Mainnet's block gas limit, used as the upper bound for [`Simulator::estimate_gas`].
+const BLOCK_GAS_LIMIT: u64 = 30_000_000;
+
+/// Lower bound for [`Simulator::estimate_gas`]'s binary search.
+const MIN_GAS_LIMIT: u64 = 21_000;
+
+/// Parameters for a single simulated call.
+#[derive(Clone, Debug)]
+pub struct CallOpts {
+    pub from: Address,
+    pub to: Address,
+    pub value: U256,
+    pub data: Bytes,
+    pub gas_limit: u64,
+}
+
+#[derive(Debug)]
+pub enum SimulatorError {
+    Backend(BackendError),
+    Revert(Bytes),
+    Halt(HaltReason),
+    /// Any other EVM-level failure (e.g. an invalid transaction env).
+    Evm(String),
+}
+
+impl std::fmt::Display for SimulatorError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SimulatorError::Backend(err) => write!(f, "backend error: {err}"),
+            SimulatorError::Revert(data) => write!(f, "execution reverted: {data:#x}"),
+            SimulatorError::Halt(reason) => write!(f, "execution halted: {reason:?}"),
+            SimulatorError::Evm(err) => write!(f, "evm error: {err}"),
+        }
+    }
+}
+
+impl std::error::Error for SimulatorError {}
+
+fn to_simulator_error(err: EVMError<BackendError>) -> SimulatorError {
+    match err {
+        EVMError::Database(err) => SimulatorError::Backend(err),
+        other => SimulatorError::Evm(format!("{other:?}")),
+    }
+}
+
+/// A thin driver around [`ForkDB`] offering `call`/`commit`/`estimate_gas`.
+pub struct Simulator<'a> {
+    fork_db: &'a mut ForkDB,
+}
+
+impl<'a> Simulator<'a> {
+    pub fn new(fork_db: &'a mut ForkDB) -> Self {
+        Self { fork_db }
+    }
+
+    /// Runs `opts` without committing state changes and returns the decoded call output.
+    pub fn call(&mut self, opts: CallOpts) -> Result<Bytes, SimulatorError> {
+        match self.run(&opts)? {
+            ExecutionResult::Success { output: Output::Call(data), .. } => Ok(data),
+            ExecutionResult::Success { output: Output::Create(data, _), .. } => Ok(data),
+            ExecutionResult::Revert { output, .. } => Err(SimulatorError::Revert(output)),
+            ExecutionResult::Halt { reason, .. } => Err(SimulatorError::Halt(reason)),
+        }
+    }
+
+    /// Runs `opts` and commits the resulting state changes to the fork.
+    pub fn commit(&mut self, opts: CallOpts) -> Result<ExecutionResult, SimulatorError> {
+        let mut evm = Evm::builder()
+            .with_db(&mut *self.fork_db)
+            .modify_tx_env(|tx| apply_call_opts(tx, &opts))
+            .build();
+
+        evm.transact_commit().map_err(to_simulator_error)
+    }
+
+    /// Binary-searches for the lowest gas limit that still lets `opts` succeed.
+    /// `opts.gas_limit` is ignored.
+    pub fn estimate_gas(&mut self, opts: CallOpts) -> Result<u64, SimulatorError> {
+        let mut probe = opts.clone();
+        probe.gas_limit = BLOCK_GAS_LIMIT;
+
+        match self.run(&probe)? {
+            ExecutionResult::Revert { output, .. } => return Err(SimulatorError::Revert(output)),
+            ExecutionResult::Halt { reason, .. } => return Err(SimulatorError::Halt(reason)),
+            ExecutionResult::Success { .. } => {}
+        }
+
+        let mut low = MIN_GAS_LIMIT;
+        let mut high = BLOCK_GAS_LIMIT;
+        while low < high {
+            let mid = low + (high - low) / 2;
+            let mut attempt = opts.clone();
+            attempt.gas_limit = mid;
+
+            // A limit below the call's intrinsic gas is rejected before
+            // execution; that counts as too little gas, not as a failure.
+            match self.run(&attempt) {
+                Ok(ExecutionResult::Success { .. }) => high = mid,
+                Ok(_) | Err(SimulatorError::Evm(_)) => low = mid + 1,
+                Err(err) => return Err(err),
+            }
+        }
+
+        Ok(high)
+    }
+
+    fn run(&mut self, opts: &CallOpts) -> Result<ExecutionResult, SimulatorError> {
+        let mut evm = Evm::builder()
+            .with_db(&mut *self.fork_db)
+            .modify_tx_env(|tx| apply_call_opts(tx, opts))
+            .build();
+
+        evm.transact().map(|result_and_state| result_and_state.result).map_err(to_simulator_error)
+    }
+}
+
+fn apply_call_opts(tx: &mut revm::primitives::TxEnv, opts: &CallOpts) {
+    tx.caller = opts.from;
+    tx.transact_to = TxKind::Call(opts.to);
+    tx.data = opts.data.clone();
+    tx.value = opts.value;
+    tx.gas_limit = opts.gas_limit;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use revm::{
+        db::{CacheDB, EmptyDB},
+        primitives::{AccountInfo as RevmAccountInfo, Bytecode, B256},
+    };
+    use std::{collections::HashMap, sync::Arc};
+
+    use crate::fork_db::{EthProvider, PrefetchedValue};
+
+    /// An [`EthProvider`] backed by an in-memory map instead of a live RPC
+    /// endpoint, so `estimate_gas`'s search can be tested without a node.
+    #[derive(Default)]
+    struct FakeProvider {
+        accounts: HashMap<Address, RevmAccountInfo>,
+    }
+
+    impl EthProvider for FakeProvider {
+        fn get_basic(&self, address: Address) -> Result<Option<RevmAccountInfo>, BackendError> {
+            Ok(self.accounts.get(&address).cloned())
+        }
+
+        fn get_storage(&self, _address: Address, _index: U256) -> Result<U256, BackendError> {
+            Ok(U256::ZERO)
+        }
+
+        fn get_block_hash(&self, _number: U256) -> Result<B256, BackendError> {
+            Ok(B256::ZERO)
+        }
+
+        fn prefetch(&self, _accounts: &[(Address, Vec<U256>)]) -> Result<Vec<PrefetchedValue>, BackendError> {
+            Ok(Vec::new())
+        }
+    }
+
+    fn keccak_hash(data: &[u8]) -> B256 {
+        use sha3::{Digest, Keccak256};
+        let mut hasher = Keccak256::new();
+        hasher.update(data);
+        B256::from_slice(&hasher.finalize())
+    }
+
+    fn fork_db_with(provider: FakeProvider) -> ForkDB {
+        ForkDB::new(CacheDB::new(EmptyDB::default()), Arc::new(provider))
+    }
+
+    #[test]
+    fn estimate_gas_converges_to_the_intrinsic_floor_for_a_plain_transfer() {
+        let mut db = fork_db_with(FakeProvider::default());
+        let mut sim = Simulator::new(&mut db);
+
+        let opts = CallOpts {
+            from: Address::ZERO,
+            to: Address::from([9u8; 20]),
+            value: U256::ZERO,
+            data: Bytes::new(),
+            gas_limit: 0,
+        };
+
+        assert_eq!(sim.estimate_gas(opts).unwrap(), MIN_GAS_LIMIT);
+    }
+
+    #[test]
+    fn estimate_gas_reports_a_revert_instead_of_searching() {
+        let to = Address::from([8u8; 20]);
+        // PUSH1 0x00 PUSH1 0x00 REVERT: reverts no matter how much gas it's given.
+        let code: Bytes = vec![0x60, 0x00, 0x60, 0x00, 0xfd].into();
+
+        let mut provider = FakeProvider::default();
+        provider.accounts.insert(
+            to,
+            RevmAccountInfo {
+                balance: U256::ZERO,
+                nonce: 0,
+                code_hash: keccak_hash(&code),
+                code: Some(Bytecode::new_raw(code)),
+            },
+        );
+        let mut db = fork_db_with(provider);
+        let mut sim = Simulator::new(&mut db);
+
+        let opts = CallOpts {
+            from: Address::ZERO,
+            to,
+            value: U256::ZERO,
+            data: Bytes::new(),
+            gas_limit: 0,
+        };
+
+        assert!(matches!(sim.estimate_gas(opts), Err(SimulatorError::Revert(_))));
+    }
+}
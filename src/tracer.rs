@@ -0,0 +1,279 @@
+use std::collections::HashMap;
+
+use revm::{
+    inspector_handle_register,
+    interpreter::{CallInputs, CallScheme, CreateInputs, CreateScheme, Gas, InstructionResult, Interpreter},
+    primitives::{Address, Bytes, ExecutionResult, TxEnv, U256},
+    Database, EVMData, Evm, Inspector,
+};
+
+use crate::fork_db::ForkDB;
+
+/// Controls how much detail [`StructTracer`] records per step. Capturing the
+/// full stack/memory on every opcode is expensive for long-running calls, so
+/// both can be turned off for a lighter trace.
+#[derive(Clone, Copy, Debug)]
+pub struct TracerConfig {
+    pub capture_stack: bool,
+    pub capture_memory: bool,
+}
+
+impl Default for TracerConfig {
+    fn default() -> Self {
+        Self { capture_stack: true, capture_memory: true }
+    }
+}
+
+/// One entry of [`StructTracer`]'s output, shaped like
+/// `debug_traceTransaction`'s `structLogs`.
+#[derive(Clone, Debug)]
+pub struct StructLog {
+    pub pc: usize,
+    pub op: u8,
+    pub gas: u64,
+    pub gas_cost: u64,
+    pub depth: u64,
+    pub stack: Vec<U256>,
+    pub memory: Vec<u8>,
+    /// Non-empty only on an `SSTORE` step, mapping the written slot to its
+    /// new value.
+    pub storage_changes: HashMap<U256, U256>,
+}
+
+/// A CALL/DELEGATECALL/STATICCALL or CREATE made during the simulation.
+#[derive(Clone, Debug)]
+pub enum CallKind {
+    Call(CallScheme),
+    Create(CreateScheme),
+}
+
+#[derive(Clone, Debug)]
+pub struct CallLog {
+    pub kind: CallKind,
+    pub target: Address,
+    pub input: Bytes,
+    pub output: Bytes,
+    pub value: U256,
+}
+
+/// Records an opcode-level struct log plus a flat list of calls, as a
+/// programmatic alternative to revm's text-dumping `CustomPrintTracer`.
+#[derive(Default)]
+pub struct StructTracer {
+    config: TracerConfig,
+    logs: Vec<StructLog>,
+    calls: Vec<CallLog>,
+}
+
+impl StructTracer {
+    pub fn new(config: TracerConfig) -> Self {
+        Self { config, logs: Vec::new(), calls: Vec::new() }
+    }
+
+    pub fn logs(&self) -> &[StructLog] {
+        &self.logs
+    }
+
+    pub fn calls(&self) -> &[CallLog] {
+        &self.calls
+    }
+}
+
+impl<DB: Database> Inspector<DB> for StructTracer {
+    fn step(&mut self, interp: &mut Interpreter, data: &mut EVMData<'_, DB>) {
+        let mut storage_changes = HashMap::new();
+        if interp.current_opcode() == revm::interpreter::opcode::SSTORE {
+            if let (Ok(slot), Ok(value)) = (interp.stack.peek(0), interp.stack.peek(1)) {
+                storage_changes.insert(slot, value);
+            }
+        }
+
+        self.logs.push(StructLog {
+            pc: interp.program_counter(),
+            op: interp.current_opcode(),
+            gas: interp.gas.remaining(),
+            gas_cost: 0,
+            depth: data.journaled_state.depth() as u64,
+            stack: if self.config.capture_stack { interp.stack.data().clone() } else { Vec::new() },
+            memory: if self.config.capture_memory { interp.shared_memory.context_memory().to_vec() } else { Vec::new() },
+            storage_changes,
+        });
+    }
+
+    fn step_end(&mut self, interp: &mut Interpreter, _data: &mut EVMData<'_, DB>) {
+        if let Some(last) = self.logs.last_mut() {
+            last.gas_cost = last.gas.saturating_sub(interp.gas.remaining());
+        }
+    }
+
+    fn call(&mut self, _data: &mut EVMData<'_, DB>, inputs: &mut CallInputs) -> (InstructionResult, Gas, Bytes) {
+        self.calls.push(CallLog {
+            kind: CallKind::Call(inputs.context.scheme),
+            target: inputs.context.address,
+            input: inputs.input.clone(),
+            output: Bytes::new(),
+            value: inputs.transfer.value,
+        });
+        (InstructionResult::Continue, Gas::new(inputs.gas_limit), Bytes::new())
+    }
+
+    fn call_end(
+        &mut self,
+        _data: &mut EVMData<'_, DB>,
+        _inputs: &CallInputs,
+        remaining_gas: Gas,
+        ret: InstructionResult,
+        out: Bytes,
+    ) -> (InstructionResult, Gas, Bytes) {
+        if let Some(last) = self.calls.last_mut() {
+            last.output = out.clone();
+        }
+        (ret, remaining_gas, out)
+    }
+
+    fn create(&mut self, _data: &mut EVMData<'_, DB>, inputs: &mut CreateInputs) -> (InstructionResult, Option<Address>, Gas, Bytes) {
+        self.calls.push(CallLog {
+            kind: CallKind::Create(inputs.scheme),
+            target: Address::ZERO,
+            input: inputs.init_code.clone(),
+            output: Bytes::new(),
+            value: inputs.value,
+        });
+        (InstructionResult::Continue, None, Gas::new(inputs.gas_limit), Bytes::new())
+    }
+
+    fn create_end(
+        &mut self,
+        _data: &mut EVMData<'_, DB>,
+        _inputs: &CreateInputs,
+        ret: InstructionResult,
+        address: Option<Address>,
+        remaining_gas: Gas,
+        out: Bytes,
+    ) -> (InstructionResult, Option<Address>, Gas, Bytes) {
+        if let Some(last) = self.calls.last_mut() {
+            last.target = address.unwrap_or_default();
+            last.output = out.clone();
+        }
+        (ret, address, remaining_gas, out)
+    }
+}
+
+/// Runs `tx` against `fork_db` with a [`StructTracer`] attached, returning
+/// the execution result alongside the recorded struct log. Emitted logs can
+/// still be decoded off the `ExecutionResult` with `SolEvent`, same as the
+/// untraced path.
+pub fn simulate_with_trace(
+    fork_db: &mut ForkDB,
+    tx: TxEnv,
+    config: TracerConfig,
+) -> Result<(ExecutionResult, Vec<StructLog>), Box<dyn std::error::Error>> {
+    let tracer = StructTracer::new(config);
+
+    let mut evm = Evm::builder()
+        .with_db(fork_db)
+        .with_external_context(tracer)
+        .with_tx_env(tx)
+        .append_handler_register(inspector_handle_register)
+        .build();
+
+    let result = evm.transact().map_err(|e| format!("{e:?}"))?.result;
+    let logs = evm.context.external.logs().to_vec();
+
+    Ok((result, logs))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use revm::{
+        db::{CacheDB, EmptyDB},
+        primitives::{AccountInfo as RevmAccountInfo, Bytecode, TxKind, B256},
+    };
+    use std::sync::Arc;
+
+    use crate::{
+        fork_db::{EthProvider, PrefetchedValue},
+        provider::BackendError,
+    };
+
+    #[derive(Default)]
+    struct FakeProvider {
+        accounts: HashMap<Address, RevmAccountInfo>,
+    }
+
+    impl EthProvider for FakeProvider {
+        fn get_basic(&self, address: Address) -> Result<Option<RevmAccountInfo>, BackendError> {
+            Ok(self.accounts.get(&address).cloned())
+        }
+
+        fn get_storage(&self, _address: Address, _index: U256) -> Result<U256, BackendError> {
+            Ok(U256::ZERO)
+        }
+
+        fn get_block_hash(&self, _number: U256) -> Result<B256, BackendError> {
+            Ok(B256::ZERO)
+        }
+
+        fn prefetch(&self, _accounts: &[(Address, Vec<U256>)]) -> Result<Vec<PrefetchedValue>, BackendError> {
+            Ok(Vec::new())
+        }
+    }
+
+    fn keccak_hash(data: &[u8]) -> B256 {
+        use sha3::{Digest, Keccak256};
+        let mut hasher = Keccak256::new();
+        hasher.update(data);
+        B256::from_slice(&hasher.finalize())
+    }
+
+    fn fork_db_with_code(to: Address, code: Vec<u8>) -> ForkDB {
+        let mut provider = FakeProvider::default();
+        provider.accounts.insert(
+            to,
+            RevmAccountInfo {
+                balance: U256::ZERO,
+                nonce: 0,
+                code_hash: keccak_hash(&code),
+                code: Some(Bytecode::new_raw(code.into())),
+            },
+        );
+        ForkDB::new(CacheDB::new(EmptyDB::default()), Arc::new(provider))
+    }
+
+    fn call_tx(to: Address) -> TxEnv {
+        let mut tx = TxEnv::default();
+        tx.caller = Address::ZERO;
+        tx.transact_to = TxKind::Call(to);
+        tx.data = Bytes::new();
+        tx.value = U256::ZERO;
+        tx.gas_limit = 100_000;
+        tx
+    }
+
+    #[test]
+    fn struct_tracer_records_the_sstore_slot_and_value() {
+        let to = Address::from([7u8; 20]);
+        // PUSH1 0x02 PUSH1 0x01 SSTORE STOP: storage[1] = 2
+        let code = vec![0x60, 0x02, 0x60, 0x01, 0x55, 0x00];
+        let mut fork_db = fork_db_with_code(to, code);
+
+        let (_, logs) = simulate_with_trace(&mut fork_db, call_tx(to), TracerConfig::default()).unwrap();
+
+        let sstore_log = logs.iter().find(|log| !log.storage_changes.is_empty()).expect("no SSTORE step recorded");
+        assert_eq!(sstore_log.storage_changes.get(&U256::from(1u64)), Some(&U256::from(2u64)));
+    }
+
+    #[test]
+    fn struct_tracer_omits_stack_and_memory_when_disabled() {
+        let to = Address::from([6u8; 20]);
+        let code = vec![0x60, 0x02, 0x60, 0x01, 0x55, 0x00];
+        let mut fork_db = fork_db_with_code(to, code);
+
+        let config = TracerConfig { capture_stack: false, capture_memory: false };
+        let (_, logs) = simulate_with_trace(&mut fork_db, call_tx(to), config).unwrap();
+
+        assert!(!logs.is_empty());
+        assert!(logs.iter().all(|log| log.stack.is_empty() && log.memory.is_empty()));
+    }
+}